@@ -7,28 +7,49 @@
 
 //! UDP based DNS client connection for Client impls
 
+use std::future::Future;
 use std::net::SocketAddr;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use rustls::{Certificate, ClientConfig};
-use trust_dns_proto::https::{HttpsClientConnect, HttpsClientStream, HttpsClientStreamBuilder};
-use trust_dns_proto::tcp::TcpConnector;
+use futures_util::lock::Mutex;
+use rustls::{Certificate, ClientConfig, PrivateKey, TLSError};
+use trust_dns_proto::error::ProtoError;
+use trust_dns_proto::https::{HttpsClientStream, HttpsClientStreamBuilder};
+use trust_dns_proto::xfer::DnsRequestSender;
+use trust_dns_proto::RuntimeProvider;
 
 use crate::client::{ClientConnection, Signer};
 
+/// Default amount of time an idle, reused HTTP/2 connection is kept around before it is torn
+/// down in favor of dialing a fresh one.
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// A previously established HTTPS stream, kept around so `connection_reuse` can hand it back
+/// out instead of paying for a fresh TCP+TLS+h2 handshake on every query.
+struct PooledConnection<S> {
+    stream: S,
+    last_used: Instant,
+}
+
 /// UDP based DNS Client connection
 ///
 /// Use with `trust_dns_client::client::Client` impls
 #[derive(Clone)]
-pub struct HttpsClientConnection<T> {
+pub struct HttpsClientConnection<R: RuntimeProvider> {
     name_server: SocketAddr,
     dns_name: String,
     client_config: ClientConfig,
-    connector: T,
+    bind_addr: Option<SocketAddr>,
+    connection_reuse: bool,
+    idle_timeout: Duration,
+    pool: Arc<Mutex<Option<PooledConnection<HttpsClientStream<R::TcpConnection>>>>>,
+    runtime_provider: R,
 }
 
-impl<T: TcpConnector + Default> HttpsClientConnection<T> {
-    /// Creates a new client connection with a default TCP connector.
+impl<R: RuntimeProvider + Default> HttpsClientConnection<R> {
+    /// Creates a new client connection with a default runtime provider.
     ///
     /// *Note* this has side affects of binding the socket to 0.0.0.0 and starting the listening
     ///        event_loop. Expect this to change in the future.
@@ -37,66 +58,114 @@ impl<T: TcpConnector + Default> HttpsClientConnection<T> {
     ///
     /// * `name_server` - address of the name server to use for queries
     #[allow(clippy::new_ret_no_self)]
-    pub fn new() -> HttpsClientConnectionBuilder<T> {
+    pub fn new() -> HttpsClientConnectionBuilder<R> {
         HttpsClientConnectionBuilder::default()
     }
 }
 
-impl<T: TcpConnector> HttpsClientConnection<T> {
-    /// Creates a new client connection with a TCP connector.
+impl<R: RuntimeProvider> HttpsClientConnection<R> {
+    /// Creates a new client connection with the given runtime provider.
     ///
     /// *Note* this has side affects of binding the socket to 0.0.0.0 and starting the listening
     ///        event_loop. Expect this to change in the future.
     ///
     /// # Arguments
     ///
-    /// * `connector` - TCP connector to be used for establishing an HTTPS connection.
-    pub fn with_connector(connector: T) -> HttpsClientConnectionBuilder<T> {
-        HttpsClientConnectionBuilder::new(connector)
+    /// * `runtime_provider` - runtime to use for driving the TCP+TLS+h2 handshake
+    pub fn with_runtime(runtime_provider: R) -> HttpsClientConnectionBuilder<R> {
+        HttpsClientConnectionBuilder::new(runtime_provider)
     }
 }
 
-impl<T> ClientConnection for HttpsClientConnection<T>
+impl<R> ClientConnection for HttpsClientConnection<R>
 where
-    T: TcpConnector,
+    R: RuntimeProvider,
+    HttpsClientStream<R::TcpConnection>: Clone + DnsRequestSender,
 {
-    type Sender = HttpsClientStream;
-    type SenderFuture = HttpsClientConnect<T>;
+    type Sender = HttpsClientStream<R::TcpConnection>;
+    type SenderFuture = Pin<Box<dyn Future<Output = Result<Self::Sender, ProtoError>> + Send>>;
 
     fn new_stream(
         &self,
         // TODO: maybe signer needs to be applied in https...
         _signer: Option<Arc<Signer>>,
     ) -> Self::SenderFuture {
-        // TODO: maybe signer needs to be applied in https...
-        let https_builder = HttpsClientStreamBuilder::with_client_config(
-            self.connector.clone(),
-            Arc::new(self.client_config.clone()),
-        );
-        https_builder.build(self.name_server, self.dns_name.clone())
+        let client_config = Arc::new(self.client_config.clone());
+        let runtime_provider = self.runtime_provider.clone();
+        let bind_addr = self.bind_addr;
+        let name_server = self.name_server;
+        let dns_name = self.dns_name.clone();
+        let connection_reuse = self.connection_reuse;
+        let idle_timeout = self.idle_timeout;
+        let pool = self.pool.clone();
+
+        Box::pin(async move {
+            let mut pool = if connection_reuse {
+                let mut pool = pool.lock().await;
+                if let Some(pooled) = pool.as_mut() {
+                    let alive = !pooled.stream.is_shutdown()
+                        && pooled.last_used.elapsed() < idle_timeout;
+                    if alive {
+                        pooled.last_used = Instant::now();
+                        return Ok(pooled.stream.clone());
+                    }
+                }
+                Some(pool)
+            } else {
+                None
+            };
+
+            // No cached connection, connection_reuse is off, the cached stream reported itself
+            // shut down (GOAWAY/connection close), or it's past its idle timeout — dial a
+            // fresh connection.
+            let mut https_builder =
+                HttpsClientStreamBuilder::with_client_config(client_config, runtime_provider);
+            if let Some(bind_addr) = bind_addr {
+                https_builder.bind_addr(bind_addr);
+            }
+            let stream = https_builder.build(name_server, dns_name).await?;
+
+            if let Some(mut pool) = pool.take() {
+                *pool = Some(PooledConnection {
+                    stream: stream.clone(),
+                    last_used: Instant::now(),
+                });
+            }
+
+            Ok(stream)
+        })
     }
 }
 
 /// A helper to construct an HTTPS connection
-pub struct HttpsClientConnectionBuilder<T: TcpConnector> {
+pub struct HttpsClientConnectionBuilder<R: RuntimeProvider> {
     client_config: ClientConfig,
-    connector: T,
+    bind_addr: Option<SocketAddr>,
+    connection_reuse: bool,
+    idle_timeout: Duration,
+    runtime_provider: R,
 }
 
-impl<T: TcpConnector> HttpsClientConnectionBuilder<T> {
+impl<R: RuntimeProvider> HttpsClientConnectionBuilder<R> {
     /// Return a new builder for DNS-over-HTTPS
-    pub fn new(connector: T) -> HttpsClientConnectionBuilder<T> {
+    pub fn new(runtime_provider: R) -> HttpsClientConnectionBuilder<R> {
         HttpsClientConnectionBuilder {
             client_config: ClientConfig::new(),
-            connector,
+            bind_addr: None,
+            connection_reuse: false,
+            idle_timeout: DEFAULT_IDLE_TIMEOUT,
+            runtime_provider,
         }
     }
 
     /// Constructs a new TlsStreamBuilder with the associated ClientConfig
-    pub fn with_client_config(client_config: ClientConfig, connector: T) -> Self {
+    pub fn with_client_config(client_config: ClientConfig, runtime_provider: R) -> Self {
         HttpsClientConnectionBuilder {
             client_config,
-            connector,
+            bind_addr: None,
+            connection_reuse: false,
+            idle_timeout: DEFAULT_IDLE_TIMEOUT,
+            runtime_provider,
         }
     }
 
@@ -110,27 +179,95 @@ impl<T: TcpConnector> HttpsClientConnectionBuilder<T> {
             .expect("bad certificate!");
     }
 
+    /// Populate the trusted root store with the OS's native certificate store, via
+    /// `rustls-native-certs`.
+    #[cfg(feature = "native-certs")]
+    pub fn add_native_certs(&mut self) -> Result<(), std::io::Error> {
+        for cert in rustls_native_certs::load_native_certs()? {
+            // Skip certs that rustls can't parse rather than failing the whole load, since a
+            // single malformed OS entry shouldn't prevent trusting the rest of the store.
+            let _ = self
+                .client_config
+                .root_store
+                .add(&Certificate(cert.0));
+        }
+
+        Ok(())
+    }
+
+    /// Populate the trusted root store with the bundled Mozilla root CAs from `webpki-roots`.
+    #[cfg(feature = "webpki-roots")]
+    pub fn add_webpki_roots(&mut self) {
+        self.client_config
+            .root_store
+            .add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
+    }
+
+    /// Present a client identity for mutual TLS authentication against DoH servers that
+    /// require it.
+    ///
+    /// # Arguments
+    ///
+    /// * `cert_chain` - certificate chain for the client identity, leaf certificate first
+    /// * `key` - private key matching the leaf certificate in `cert_chain`
+    pub fn set_client_auth(
+        &mut self,
+        cert_chain: Vec<Certificate>,
+        key: PrivateKey,
+    ) -> Result<(), TLSError> {
+        self.client_config.set_single_client_cert(cert_chain, key)
+    }
+
+    /// Set the local address to bind the outbound socket to before connecting. Support for
+    /// this depends on the `RuntimeProvider` in use: the `async-std-runtime` provider does
+    /// not currently implement it and will fail every connection attempt once set.
+    pub fn with_bind_addr(&mut self, bind_addr: SocketAddr) {
+        self.bind_addr = Some(bind_addr);
+    }
+
+    /// Keep a single established HTTP/2 connection alive and issue concurrent DNS queries as
+    /// separate streams over it, instead of dialing a fresh TCP+TLS+h2 connection per query.
+    /// Before handing the cached connection back out, it is re-dialed if `is_shutdown()`
+    /// reports the peer closed it (e.g. GOAWAY) or if it has sat idle past the configured
+    /// idle timeout (see [`Self::with_idle_timeout`], default 5 minutes).
+    pub fn with_connection_reuse(&mut self, connection_reuse: bool) {
+        self.connection_reuse = connection_reuse;
+    }
+
+    /// Set how long a reused, idle HTTP/2 connection is kept alive before being closed. Only
+    /// takes effect when [`Self::with_connection_reuse`] is enabled.
+    pub fn with_idle_timeout(&mut self, idle_timeout: Duration) {
+        self.idle_timeout = idle_timeout;
+    }
+
     /// Creates a new HttpsStream to the specified name_server
     ///
     /// # Arguments
     ///
     /// * `name_server` - IP and Port for the remote DNS resolver
     /// * `dns_name` - The DNS name, Subject Public Key Info (SPKI) name, as associated to a certificate
-    pub fn build(self, name_server: SocketAddr, dns_name: String) -> HttpsClientConnection<T> {
+    pub fn build(self, name_server: SocketAddr, dns_name: String) -> HttpsClientConnection<R> {
         HttpsClientConnection {
             name_server,
             dns_name,
             client_config: self.client_config,
-            connector: self.connector,
+            bind_addr: self.bind_addr,
+            connection_reuse: self.connection_reuse,
+            idle_timeout: self.idle_timeout,
+            pool: Arc::new(Mutex::new(None)),
+            runtime_provider: self.runtime_provider,
         }
     }
 }
 
-impl<T: TcpConnector + Default> Default for HttpsClientConnectionBuilder<T> {
+impl<R: RuntimeProvider + Default> Default for HttpsClientConnectionBuilder<R> {
     fn default() -> Self {
         Self {
             client_config: ClientConfig::new(),
-            connector: Default::default(),
+            bind_addr: None,
+            connection_reuse: false,
+            idle_timeout: DEFAULT_IDLE_TIMEOUT,
+            runtime_provider: Default::default(),
         }
     }
 }
@@ -14,11 +14,24 @@ pub trait RuntimeProvider: Clone + 'static + Send + Sync + Unpin {
     /// Socket type that is returned after a successful connection.
     type TcpConnection: DnsTcpStream;
 
-    /// Bind an UDP socket to the given socket address.
+    /// Bind an UDP socket to the given local address, which the caller has already chosen
+    /// (e.g. to pin outbound queries to a particular interface or source IP).
     async fn bind_udp(&self, addr: SocketAddr) -> io::Result<Self::UdpSocket>;
 
-    /// Create a socket and connect to the specified socket address.
-    async fn connect_tcp(self, addr: SocketAddr) -> io::Result<Self::TcpConnection>;
+    /// Create a socket, optionally bind it to `bind_addr`, and connect to `addr`.
+    ///
+    /// `bind_addr` is the local address to bind the socket to before connecting, allowing
+    /// callers to pin outbound queries to a particular interface or source IP. When `None`,
+    /// the socket is left unbound and the OS picks the local address as usual.
+    ///
+    /// Not every implementation can honor `bind_addr` (the `async-std-runtime` one currently
+    /// returns an error rather than silently ignoring it); check the implementation in use
+    /// before relying on it.
+    async fn connect_tcp(
+        self,
+        addr: SocketAddr,
+        bind_addr: Option<SocketAddr>,
+    ) -> io::Result<Self::TcpConnection>;
 
     /// Spawn a future on the given runtime.
     fn spawn_bg<F>(&mut self, future: F)
@@ -51,10 +64,18 @@ mod tokio_runtime {
         async fn connect_tcp(
             self,
             addr: std::net::SocketAddr,
+            bind_addr: Option<std::net::SocketAddr>,
         ) -> std::io::Result<Self::TcpConnection> {
-            tokio::net::TcpStream::connect(addr)
-                .await
-                .map(AsyncIoTokioAsStd)
+            let socket = match addr {
+                std::net::SocketAddr::V4(_) => tokio::net::TcpSocket::new_v4(),
+                std::net::SocketAddr::V6(_) => tokio::net::TcpSocket::new_v6(),
+            }?;
+
+            if let Some(bind_addr) = bind_addr {
+                socket.bind(bind_addr)?;
+            }
+
+            socket.connect(addr).await.map(AsyncIoTokioAsStd)
         }
 
         fn spawn_bg<F>(&mut self, future: F)
@@ -64,4 +85,132 @@ mod tokio_runtime {
             let _join = tokio::spawn(future);
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::net::Ipv4Addr;
+
+        #[tokio::test]
+        async fn connect_tcp_binds_to_requested_local_address() {
+            let listener = tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+                .await
+                .unwrap();
+            let server_addr = listener.local_addr().unwrap();
+
+            // Reserve a specific local port, then release it so `connect_tcp` can bind it:
+            // binding to `(LOCALHOST, 0)` and only checking the peer IP proves nothing, since
+            // that's true of any loopback connection whether or not `bind_addr` was honored.
+            let bind_addr = {
+                let reserved = std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+                reserved.local_addr().unwrap()
+            };
+
+            let (accepted, connected) = tokio::join!(
+                listener.accept(),
+                TokioRuntime.connect_tcp(server_addr, Some(bind_addr)),
+            );
+
+            let (_socket, peer_addr) = accepted.unwrap();
+            connected.unwrap();
+            assert_eq!(peer_addr, bind_addr);
+        }
+    }
+}
+
+#[cfg(feature = "async-std-runtime")]
+pub use async_std_runtime::{AsyncStdRuntime, AsyncStdTime};
+
+#[cfg(feature = "async-std-runtime")]
+mod async_std_runtime {
+    use super::*;
+    use std::time::Duration;
+
+    /// An implementation of a runtime provider using the async-std runtime.
+    #[derive(Clone, Default, Copy)]
+    pub struct AsyncStdRuntime;
+
+    #[async_trait::async_trait]
+    impl RuntimeProvider for AsyncStdRuntime {
+        type Time = AsyncStdTime;
+        type UdpSocket = async_std::net::UdpSocket;
+        type TcpConnection = async_std::net::TcpStream;
+
+        async fn bind_udp(&self, addr: std::net::SocketAddr) -> std::io::Result<Self::UdpSocket> {
+            Self::UdpSocket::bind(addr).await
+        }
+
+        // async-std has no socket builder for binding a specific local address before
+        // connecting, so `bind_addr` isn't honored yet: a `Some` value returns an
+        // `ErrorKind::Unsupported` error up front rather than silently connecting from
+        // whatever local address the OS happens to pick.
+        async fn connect_tcp(
+            self,
+            addr: std::net::SocketAddr,
+            bind_addr: Option<std::net::SocketAddr>,
+        ) -> std::io::Result<Self::TcpConnection> {
+            match bind_addr {
+                Some(_) => Err(std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "binding to a local address is not supported by the async-std runtime",
+                )),
+                None => async_std::net::TcpStream::connect(addr).await,
+            }
+        }
+
+        fn spawn_bg<F>(&mut self, future: F)
+        where
+            F: Future<Output = Result<(), ProtoError>> + Send + 'static,
+        {
+            let _join = async_std::task::spawn(future);
+        }
+    }
+
+    /// An implementation of the `Time` trait using the async-std runtime's timers.
+    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+    pub struct AsyncStdTime;
+
+    #[async_trait::async_trait]
+    impl crate::Time for AsyncStdTime {
+        async fn delay_for(duration: Duration) {
+            async_std::task::sleep(duration).await
+        }
+
+        async fn timeout<F: 'static + Future + Send>(
+            duration: Duration,
+            future: F,
+        ) -> Result<F::Output, std::io::Error> {
+            async_std::future::timeout(duration, future)
+                .await
+                .map_err(|_| std::io::Error::new(std::io::ErrorKind::TimedOut, "timed out"))
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::Time;
+        use std::time::Instant;
+
+        #[async_std::test]
+        async fn delay_for_waits_at_least_the_requested_duration() {
+            let start = Instant::now();
+            AsyncStdTime::delay_for(Duration::from_millis(20)).await;
+            assert!(start.elapsed() >= Duration::from_millis(20));
+        }
+
+        #[async_std::test]
+        async fn timeout_returns_ok_when_future_completes_in_time() {
+            let result = AsyncStdTime::timeout(Duration::from_secs(5), async { 42 }).await;
+            assert_eq!(result.unwrap(), 42);
+        }
+
+        #[async_std::test]
+        async fn timeout_returns_err_when_future_is_too_slow() {
+            let result =
+                AsyncStdTime::timeout(Duration::from_millis(10), async_std::future::pending::<()>())
+                    .await;
+            assert!(result.is_err());
+        }
+    }
 }
@@ -21,12 +21,16 @@ use proto::RuntimeProvider;
 #[allow(clippy::type_complexity)]
 pub(crate) fn new_tls_stream<R: RuntimeProvider>(
     socket_addr: SocketAddr,
+    bind_addr: Option<SocketAddr>,
     dns_name: String,
     runtime: R,
 ) -> (
     Pin<Box<dyn Future<Output = Result<TlsClientStream<R::TcpConnection>, ProtoError>> + Send>>,
     BufDnsStreamHandle,
 ) {
-    let tls_builder = TlsClientStreamBuilder::new(runtime);
+    let mut tls_builder = TlsClientStreamBuilder::new(runtime);
+    if let Some(bind_addr) = bind_addr {
+        tls_builder.bind_addr(bind_addr);
+    }
     tls_builder.build(socket_addr, dns_name)
 }